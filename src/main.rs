@@ -1,14 +1,23 @@
 use camino::{Utf8Path, Utf8PathBuf, absolute_utf8};
 use clap::Parser as _;
 use etcetera::app_strategy::{AppStrategy as _, AppStrategyArgs, Xdg};
+use futures_util::{Stream, StreamExt as _, TryStreamExt as _, stream};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use jiff::Timestamp;
 use pathdiff::diff_utf8_paths;
+use serde_json::Value;
 use sqlx::{
-    Row as _, SqlitePool,
-    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous},
+    Column as _, Row as _, SqlitePool, TypeInfo as _, ValueRef as _,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteRow, SqliteSynchronous},
 };
-use std::{cmp::Ordering, collections::HashMap, env, str::FromStr as _};
-use tokio::{fs, process};
+use std::{cmp::Ordering, env, pin::Pin, process::Stdio, str::FromStr as _};
+use tokio::{
+    fs,
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    process,
+};
+
+type RankedStream<'a> = Pin<Box<dyn Stream<Item = anyhow::Result<(Utf8PathBuf, f64)>> + Send + 'a>>;
 
 #[derive(clap::Parser, Debug)]
 #[command(disable_help_subcommand = true)]
@@ -44,6 +53,18 @@ enum Command {
         /// Print absolute paths
         #[arg(long)]
         absolute: bool,
+
+        /// Print the computed score alongside each path
+        #[arg(long)]
+        score: bool,
+
+        /// Select a path interactively with fzf
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Only consider paths recorded on this Git branch
+        #[arg(long, value_name = "NAME")]
+        branch: Option<String>,
     },
 
     /// Print most recently accessed paths
@@ -51,6 +72,18 @@ enum Command {
         /// Print absolute paths
         #[arg(long)]
         absolute: bool,
+
+        /// Print the computed score alongside each path
+        #[arg(long)]
+        score: bool,
+
+        /// Select a path interactively with fzf
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Only consider paths recorded on this Git branch
+        #[arg(long, value_name = "NAME")]
+        branch: Option<String>,
     },
 
     /// Print most frequently accessed paths
@@ -58,7 +91,47 @@ enum Command {
         /// Print absolute paths
         #[arg(long)]
         absolute: bool,
+
+        /// Print the computed score alongside each path
+        #[arg(long)]
+        score: bool,
+
+        /// Select a path interactively with fzf
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Only consider paths recorded on this Git branch
+        #[arg(long, value_name = "NAME")]
+        branch: Option<String>,
+    },
+
+    /// Run an ad-hoc SQL query against the access database
+    Sql {
+        /// SQL statement to execute (reads from stdin if omitted)
+        query: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = SqlFormat::Table)]
+        format: SqlFormat,
     },
+
+    /// Seed the access database from Git history
+    Import {
+        /// Only import commits authored by the current Git user
+        #[arg(long)]
+        mine: bool,
+
+        /// Limit how many commits to walk
+        #[arg(long, value_name = "N")]
+        max_commits: Option<u32>,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum SqlFormat {
+    Table,
+    Tsv,
+    Json,
 }
 
 #[tokio::main]
@@ -74,8 +147,12 @@ async fn main() -> anyhow::Result<()> {
     })?;
 
     let state_dir = Utf8PathBuf::try_from(xdg.state_dir().unwrap())?;
+    let config_dir = Utf8PathBuf::try_from(xdg.config_dir())?;
 
     fs::create_dir_all(&state_dir).await?;
+    fs::create_dir_all(&config_dir).await?;
+
+    let exclude = load_excludes(&config_dir).await?;
 
     let sqlite_path = state_dir.join("state.sqlite3");
 
@@ -96,14 +173,15 @@ async fn main() -> anyhow::Result<()> {
         None => repo().await?,
     };
 
+    let branch = branch(&repo).await?;
+
     match args.command {
         Command::Record { time, paths } => {
             for path in &paths {
                 let path = absolute_utf8(path)?;
-                // TODO: Allow recording files outside of repo? Need to exclude temporary files like
-                // `*.jjdescription` and such.
-                if path.starts_with(&repo) {
-                    record(&sqlite, &repo, &path, time.as_ref()).await?;
+                // TODO: Allow recording files outside of repo?
+                if path.starts_with(&repo) && !exclude.is_match(&path) {
+                    record(&sqlite, &repo, &path, time.as_ref(), branch.as_deref()).await?;
                 }
             }
         }
@@ -114,44 +192,49 @@ async fn main() -> anyhow::Result<()> {
                 forget(&sqlite, &repo, &path).await?;
             }
         }
-        Command::Frecent { absolute } => {
-            for path in frecent(&sqlite, &repo).await? {
-                if !path.try_exists().unwrap_or(false) {
-                    continue;
-                }
-                let path = if absolute {
-                    path
-                } else {
-                    diff_utf8_paths(&path, &current_dir).unwrap()
-                };
-                println!("{path}");
-            }
+        Command::Frecent {
+            absolute,
+            score,
+            interactive,
+            branch,
+        } => {
+            let results = frecent(&sqlite, &repo, branch.as_deref()).await?;
+            let results = purge_stale(&sqlite, &repo, &exclude, results);
+            present(results, absolute, score, interactive, &current_dir).await?;
         }
-        Command::Recent { absolute } => {
-            for path in recent(&sqlite, &repo).await? {
-                if !path.try_exists().unwrap_or(false) {
-                    continue;
-                }
-                let path = if absolute {
-                    path
-                } else {
-                    diff_utf8_paths(&path, &current_dir).unwrap()
-                };
-                println!("{path}");
-            }
+        Command::Recent {
+            absolute,
+            score,
+            interactive,
+            branch,
+        } => {
+            let results = recent(&sqlite, &repo, branch.as_deref()).await?;
+            let results = purge_stale(&sqlite, &repo, &exclude, results);
+            present(results, absolute, score, interactive, &current_dir).await?;
+        }
+        Command::Frequent {
+            absolute,
+            score,
+            interactive,
+            branch,
+        } => {
+            let results = frequent(&sqlite, &repo, branch.as_deref()).await?;
+            let results = purge_stale(&sqlite, &repo, &exclude, results);
+            present(results, absolute, score, interactive, &current_dir).await?;
         }
-        Command::Frequent { absolute } => {
-            for path in frequent(&sqlite, &repo).await? {
-                if !path.try_exists().unwrap_or(false) {
-                    continue;
+        Command::Sql { query, format } => {
+            let query = match query {
+                Some(query) => query,
+                None => {
+                    let mut query = String::new();
+                    tokio::io::stdin().read_to_string(&mut query).await?;
+                    query
                 }
-                let path = if absolute {
-                    path
-                } else {
-                    diff_utf8_paths(&path, &current_dir).unwrap()
-                };
-                println!("{path}");
-            }
+            };
+            run_sql(&sqlite, &query, format).await?;
+        }
+        Command::Import { mine, max_commits } => {
+            import(&sqlite, &repo, &exclude, mine, max_commits).await?;
         }
     }
 
@@ -159,19 +242,160 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn sqlite_init(sqlite: &SqlitePool) -> anyhow::Result<()> {
+    migrate_to_aggregate_schema(sqlite).await?;
+
     sqlx::query(
         "
         create table if not exists empath (
             repo text not null,
             path text not null,
-            time text not null,
-            unique (repo, path, time)
+            rank real not null,
+            last_accessed text not null,
+            unique (repo, path)
         ) strict;
         ",
     )
     .execute(sqlite)
     .await?;
 
+    // Per-branch breakdown, so `--branch` can rank paths by the access history on that branch
+    // specifically rather than the repo-wide aggregate in `empath`.
+    sqlx::query(
+        "
+        create table if not exists empath_branch (
+            repo text not null,
+            path text not null,
+            branch text not null,
+            rank real not null,
+            last_accessed text not null,
+            unique (repo, path, branch)
+        ) strict;
+        ",
+    )
+    .execute(sqlite)
+    .await?;
+
+    sqlx::query(
+        "
+        create table if not exists empath_import (
+            repo text not null,
+            commit_hash text not null,
+            primary key (repo)
+        ) strict;
+        ",
+    )
+    .execute(sqlite)
+    .await?;
+
+    Ok(())
+}
+
+async fn migrate_to_aggregate_schema(sqlite: &SqlitePool) -> anyhow::Result<()> {
+    let has_time_column: Option<i64> =
+        sqlx::query_scalar("select 1 from pragma_table_info('empath') where name = 'time'")
+            .fetch_optional(sqlite)
+            .await?;
+
+    let has_branch_column: Option<i64> =
+        sqlx::query_scalar("select 1 from pragma_table_info('empath') where name = 'branch'")
+            .fetch_optional(sqlite)
+            .await?;
+
+    if has_time_column.is_none() && has_branch_column.is_none() {
+        return Ok(());
+    }
+
+    let mut txn = sqlite.begin().await?;
+
+    sqlx::query(
+        "
+        create table if not exists empath_branch (
+            repo text not null,
+            path text not null,
+            branch text not null,
+            rank real not null,
+            last_accessed text not null,
+            unique (repo, path, branch)
+        ) strict;
+        ",
+    )
+    .execute(&mut *txn)
+    .await?;
+
+    if has_time_column.is_some() {
+        sqlx::query("alter table empath rename to empath_pre_aggregate")
+            .execute(&mut *txn)
+            .await?;
+
+        sqlx::query(
+            "
+            create table empath (
+                repo text not null,
+                path text not null,
+                rank real not null,
+                last_accessed text not null,
+                unique (repo, path)
+            ) strict;
+            ",
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        sqlx::query(
+            "
+            insert into empath (repo, path, rank, last_accessed)
+            select repo, path, count(*), max(time)
+            from empath_pre_aggregate
+            group by repo, path
+            ",
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        let pre_aggregate_has_branch_column: Option<i64> = sqlx::query_scalar(
+            "select 1 from pragma_table_info('empath_pre_aggregate') where name = 'branch'",
+        )
+        .fetch_optional(&mut *txn)
+        .await?;
+
+        if pre_aggregate_has_branch_column.is_some() {
+            sqlx::query(
+                "
+                insert into empath_branch (repo, path, branch, rank, last_accessed)
+                select repo, path, branch, count(*), max(time)
+                from empath_pre_aggregate
+                where branch is not null
+                group by repo, path, branch
+                ",
+            )
+            .execute(&mut *txn)
+            .await?;
+        }
+
+        sqlx::query("drop table empath_pre_aggregate")
+            .execute(&mut *txn)
+            .await?;
+    } else if has_branch_column.is_some() {
+        // Best-effort recovery: only the branch last touched per path survived, so it seeds a
+        // single per-branch row rather than a true per-branch breakdown.
+        sqlx::query(
+            "
+            insert into empath_branch (repo, path, branch, rank, last_accessed)
+            select repo, path, branch, rank, last_accessed
+            from empath
+            where branch is not null
+            ",
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        sqlx::query("alter table empath drop column branch")
+            .execute(&mut *txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+
     Ok(())
 }
 
@@ -191,11 +415,37 @@ async fn repo() -> anyhow::Result<Utf8PathBuf> {
     Ok(repo)
 }
 
+async fn branch(repo: &Utf8Path) -> anyhow::Result<Option<String>> {
+    let output = process::Command::new("git")
+        .arg("-C")
+        .arg(repo.as_str())
+        .arg("rev-parse")
+        .arg("--abbrev-ref")
+        .arg("HEAD")
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let branch = str::from_utf8(&output.stdout)?.trim().to_string();
+
+    // In detached HEAD, `--abbrev-ref HEAD` succeeds and prints the literal name `HEAD` rather
+    // than failing, so it has to be filtered out by hand to return `None` for a detached HEAD.
+    if branch == "HEAD" {
+        return Ok(None);
+    }
+
+    Ok(Some(branch))
+}
+
 async fn record(
     sqlite: &SqlitePool,
     repo: &Utf8Path,
     path: &Utf8Path,
     time: Option<&Timestamp>,
+    branch: Option<&str>,
 ) -> anyhow::Result<()> {
     let repo = repo.as_str();
     let path = path.as_str();
@@ -205,16 +455,126 @@ async fn record(
         None => Timestamp::now().to_string(),
     };
 
-    sqlx::query("insert into empath (repo, path, time) values ($1, $2, $3)")
+    sqlx::query(
+        "
+        insert into empath (repo, path, rank, last_accessed)
+        values ($1, $2, 1.0, $3)
+        on conflict (repo, path) do update set
+            rank = rank + 1.0,
+            last_accessed = excluded.last_accessed
+        ",
+    )
+    .bind(repo)
+    .bind(path)
+    .bind(&time)
+    .execute(sqlite)
+    .await?;
+
+    if let Some(branch) = branch {
+        sqlx::query(
+            "
+            insert into empath_branch (repo, path, branch, rank, last_accessed)
+            values ($1, $2, $3, 1.0, $4)
+            on conflict (repo, path, branch) do update set
+                rank = rank + 1.0,
+                last_accessed = excluded.last_accessed
+            ",
+        )
         .bind(repo)
         .bind(path)
-        .bind(time)
+        .bind(branch)
+        .bind(&time)
         .execute(sqlite)
         .await?;
+    }
+
+    age(sqlite, repo).await?;
 
     Ok(())
 }
 
+// https://github.com/ajeetdsouza/zoxide/wiki/Algorithm#aging
+const AGE_CAP: f64 = 9000.0;
+const AGE_DECAY: f64 = 0.9;
+const AGE_FLOOR: f64 = 1.0;
+
+async fn age(sqlite: &SqlitePool, repo: &str) -> anyhow::Result<()> {
+    age_table(sqlite, "empath", repo).await?;
+    age_table(sqlite, "empath_branch", repo).await?;
+
+    Ok(())
+}
+
+async fn age_table(sqlite: &SqlitePool, table: &str, repo: &str) -> anyhow::Result<()> {
+    let total: f64 =
+        sqlx::query_scalar(&format!("select coalesce(sum(rank), 0.0) from {table} where repo = $1"))
+            .bind(repo)
+            .fetch_one(sqlite)
+            .await?;
+
+    if total <= AGE_CAP {
+        return Ok(());
+    }
+
+    sqlx::query(&format!("update {table} set rank = rank * $2 where repo = $1"))
+        .bind(repo)
+        .bind(AGE_DECAY)
+        .execute(sqlite)
+        .await?;
+
+    sqlx::query(&format!("delete from {table} where repo = $1 and rank < $2"))
+        .bind(repo)
+        .bind(AGE_FLOOR)
+        .execute(sqlite)
+        .await?;
+
+    Ok(())
+}
+
+async fn load_excludes(config_dir: &Utf8Path) -> anyhow::Result<GlobSet> {
+    let path = config_dir.join("exclude");
+
+    let mut builder = GlobSetBuilder::new();
+
+    if let Ok(contents) = fs::read_to_string(&path).await {
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            builder.add(Glob::new(line)?);
+        }
+    }
+
+    Ok(builder.build()?)
+}
+
+fn purge_stale<'a>(
+    sqlite: &'a SqlitePool,
+    repo: &'a Utf8Path,
+    exclude: &'a GlobSet,
+    results: RankedStream<'a>,
+) -> RankedStream<'a> {
+    results
+        .filter_map(move |item| async move {
+            let (path, score) = match item {
+                Ok(item) => item,
+                Err(err) => return Some(Err(err)),
+            };
+
+            let stale = !path.try_exists().unwrap_or(false) || exclude.is_match(&path);
+            if !stale {
+                return Some(Ok((path, score)));
+            }
+
+            match forget(sqlite, repo, &path).await {
+                Ok(()) => None,
+                Err(err) => Some(Err(err)),
+            }
+        })
+        .boxed()
+}
+
 async fn forget(sqlite: &SqlitePool, repo: &Utf8Path, path: &Utf8Path) -> anyhow::Result<()> {
     let repo = repo.as_str();
     let path = path.as_str();
@@ -225,93 +585,500 @@ async fn forget(sqlite: &SqlitePool, repo: &Utf8Path, path: &Utf8Path) -> anyhow
         .execute(sqlite)
         .await?;
 
+    sqlx::query("delete from empath_branch where repo = $1 and path = $2")
+        .bind(repo)
+        .bind(path)
+        .execute(sqlite)
+        .await?;
+
     Ok(())
 }
 
+fn row_to_path_score(row: Result<SqliteRow, sqlx::Error>) -> anyhow::Result<(Utf8PathBuf, f64)> {
+    let row = row?;
+    let path: String = row.get("path");
+    let score: f64 = row.get("score");
+    Ok((Utf8PathBuf::from(path), score))
+}
+
 // https://wiki.mozilla.org/User:Jesse/NewFrecency
-async fn frecent(sqlite: &SqlitePool, repo: &Utf8Path) -> anyhow::Result<Vec<Utf8PathBuf>> {
+async fn frecent(
+    sqlite: &SqlitePool,
+    repo: &Utf8Path,
+    branch: Option<&str>,
+) -> anyhow::Result<RankedStream<'static>> {
     let repo = repo.as_str();
 
-    let rows = sqlx::query(
-        "
-        select
-            path,
-            julianday('now') - julianday(time) as age_days
-        from empath
-        where repo = $1
-        ",
-    )
-    .bind(repo)
-    .fetch_all(sqlite)
-    .await?;
+    let rows = if let Some(branch) = branch {
+        sqlx::query(
+            "
+            select
+                path,
+                rank,
+                julianday('now') - julianday(last_accessed) as age_days
+            from empath_branch
+            where repo = $1 and branch = $2
+            ",
+        )
+        .bind(repo)
+        .bind(branch)
+        .fetch_all(sqlite)
+        .await?
+    } else {
+        sqlx::query(
+            "
+            select
+                path,
+                rank,
+                julianday('now') - julianday(last_accessed) as age_days
+            from empath
+            where repo = $1
+            ",
+        )
+        .bind(repo)
+        .fetch_all(sqlite)
+        .await?
+    };
 
     let half_life_days = 30.0;
 
-    let mut scores = HashMap::new();
+    let mut items = rows
+        .into_iter()
+        .map(|row| {
+            let path: String = row.get("path");
+            let rank: f64 = row.get("rank");
+            let age_days: f64 = row.get("age_days");
+            let recency = 2f64.powf(-age_days / half_life_days);
+            (path, rank * recency)
+        })
+        .collect::<Vec<_>>();
 
-    for row in rows {
-        let path: String = row.get("path");
-        let age_days: f64 = row.get("age_days");
-        let weight = 2f64.powf(-age_days / half_life_days);
-        *scores.entry(path).or_insert(0.0) += weight;
+    items.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+    let results = items
+        .into_iter()
+        .map(|(path, score)| Ok((Utf8PathBuf::from(path), score)));
+
+    Ok(stream::iter(results).boxed())
+}
+
+async fn recent<'a>(
+    sqlite: &'a SqlitePool,
+    repo: &Utf8Path,
+    branch: Option<&str>,
+) -> anyhow::Result<RankedStream<'a>> {
+    let repo = repo.as_str().to_string();
+    let branch = branch.map(str::to_string);
+
+    let stream = match branch {
+        Some(branch) => sqlx::query(
+            "
+            select path, julianday(last_accessed) as score
+            from empath_branch
+            where repo = $1 and branch = $2
+            order by score desc
+            ",
+        )
+        .bind(repo)
+        .bind(branch)
+        .fetch(sqlite)
+        .map(row_to_path_score)
+        .boxed(),
+        None => sqlx::query(
+            "
+            select path, julianday(last_accessed) as score
+            from empath
+            where repo = $1
+            order by score desc
+            ",
+        )
+        .bind(repo)
+        .fetch(sqlite)
+        .map(row_to_path_score)
+        .boxed(),
+    };
+
+    Ok(stream)
+}
+
+async fn frequent<'a>(
+    sqlite: &'a SqlitePool,
+    repo: &Utf8Path,
+    branch: Option<&str>,
+) -> anyhow::Result<RankedStream<'a>> {
+    let repo = repo.as_str().to_string();
+    let branch = branch.map(str::to_string);
+
+    let stream = match branch {
+        Some(branch) => sqlx::query(
+            "
+            select path, rank as score
+            from empath_branch
+            where repo = $1 and branch = $2
+            order by score desc
+            ",
+        )
+        .bind(repo)
+        .bind(branch)
+        .fetch(sqlite)
+        .map(row_to_path_score)
+        .boxed(),
+        None => sqlx::query(
+            "
+            select path, rank as score
+            from empath
+            where repo = $1
+            order by score desc
+            ",
+        )
+        .bind(repo)
+        .fetch(sqlite)
+        .map(row_to_path_score)
+        .boxed(),
+    };
+
+    Ok(stream)
+}
+
+async fn present<'a>(
+    results: RankedStream<'a>,
+    absolute: bool,
+    score: bool,
+    interactive: bool,
+    current_dir: &'a Utf8Path,
+) -> anyhow::Result<()> {
+    let mut existing: RankedStream<'a> = results
+        .map_ok(move |(path, path_score)| {
+            let path = if absolute {
+                path
+            } else {
+                diff_utf8_paths(&path, current_dir).unwrap()
+            };
+            (path, path_score)
+        })
+        .boxed();
+
+    if interactive {
+        if let Some((path, path_score)) = fzf_select(existing, score).await? {
+            if score {
+                println!("{path}\t{path_score}");
+            } else {
+                println!("{path}");
+            }
+        }
+    } else {
+        while let Some(item) = existing.next().await {
+            let (path, path_score) = item?;
+            if score {
+                println!("{path}\t{path_score}");
+            } else {
+                println!("{path}");
+            }
+        }
     }
 
-    let mut items = scores.into_iter().collect::<Vec<_>>();
+    Ok(())
+}
 
-    items.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+async fn fzf_select(
+    mut results: RankedStream<'_>,
+    score: bool,
+) -> anyhow::Result<Option<(Utf8PathBuf, f64)>> {
+    let mut child = process::Command::new("fzf")
+        .arg("--delimiter=\t")
+        .arg(if score { "--with-nth=1,2" } else { "--with-nth=1" })
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
 
-    let paths = items
-        .into_iter()
-        .map(|(path, _)| Utf8PathBuf::from(path))
-        .collect();
+    let mut stdin = child.stdin.take().expect("fzf stdin was requested");
+
+    while let Some(item) = results.next().await {
+        let (path, path_score) = item?;
+        match stdin
+            .write_all(format!("{path}\t{path_score}\n").as_bytes())
+            .await
+        {
+            Ok(()) => {}
+            // fzf already exited (e.g. the user picked a candidate or hit Escape before we
+            // finished streaming); stop feeding it and fall through to read its output below.
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    drop(stdin);
+
+    let output = child.wait_with_output().await?;
+
+    if !output.status.success() {
+        // User cancelled the selection (e.g. pressed Escape).
+        return Ok(None);
+    }
 
-    Ok(paths)
+    let selection = String::from_utf8(output.stdout)?;
+    let selection = selection.trim();
+
+    let Some((path, path_score)) = selection.split_once('\t') else {
+        return Ok(None);
+    };
+
+    Ok(Some((Utf8PathBuf::from(path), path_score.parse()?)))
 }
 
-async fn recent(sqlite: &SqlitePool, repo: &Utf8Path) -> anyhow::Result<Vec<Utf8PathBuf>> {
-    let repo = repo.as_str();
+async fn run_sql(sqlite: &SqlitePool, query: &str, format: SqlFormat) -> anyhow::Result<()> {
+    let rows = sqlx::query(query).fetch_all(sqlite).await?;
 
-    let rows: Vec<String> = sqlx::query_scalar(
-        "
-        select path
-        from empath
-        where repo = $1
-        group by path
-        order by max(time) desc
-        ",
-    )
-    .bind(repo)
-    .fetch_all(sqlite)
-    .await?;
+    let Some(first) = rows.first() else {
+        return Ok(());
+    };
 
-    let paths = rows
-        .into_iter()
-        .map(|string| Utf8PathBuf::from(string))
-        .collect();
+    let columns = first
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect::<Vec<_>>();
+
+    let rows = rows
+        .iter()
+        .map(|row| (0..columns.len()).map(|i| cell_value(row, i)).collect())
+        .collect::<anyhow::Result<Vec<Vec<_>>>>()?;
+
+    match format {
+        SqlFormat::Table => print_sql_table(&columns, &rows),
+        SqlFormat::Tsv => print_sql_tsv(&columns, &rows),
+        SqlFormat::Json => print_sql_json(&columns, &rows)?,
+    }
 
-    Ok(paths)
+    Ok(())
 }
 
-async fn frequent(sqlite: &SqlitePool, repo: &Utf8Path) -> anyhow::Result<Vec<Utf8PathBuf>> {
-    let repo = repo.as_str();
+fn cell_value(row: &SqliteRow, index: usize) -> anyhow::Result<Value> {
+    let raw = row.try_get_raw(index)?;
 
-    let rows: Vec<String> = sqlx::query_scalar(
+    if raw.is_null() {
+        return Ok(Value::Null);
+    }
+
+    let value = match raw.type_info().name() {
+        "INTEGER" => Value::from(row.try_get::<i64, _>(index)?),
+        "REAL" => Value::from(row.try_get::<f64, _>(index)?),
+        _ => Value::from(row.try_get::<String, _>(index)?),
+    };
+
+    Ok(value)
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn print_sql_table(columns: &[String], rows: &[Vec<Value>]) {
+    let mut widths = columns
+        .iter()
+        .map(|column| column.chars().count())
+        .collect::<Vec<_>>();
+
+    let cells = rows
+        .iter()
+        .map(|row| row.iter().map(display_value).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |row: &[String]| {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+
+    print_row(columns);
+    for row in cells {
+        print_row(&row);
+    }
+}
+
+fn print_sql_tsv(columns: &[String], rows: &[Vec<Value>]) {
+    println!("{}", columns.join("\t"));
+    for row in rows {
+        let line = row.iter().map(display_value).collect::<Vec<_>>().join("\t");
+        println!("{line}");
+    }
+}
+
+fn print_sql_json(columns: &[String], rows: &[Vec<Value>]) -> anyhow::Result<()> {
+    for row in rows {
+        let object = columns
+            .iter()
+            .cloned()
+            .zip(row.iter().cloned())
+            .collect::<serde_json::Map<_, _>>();
+        println!("{}", serde_json::to_string(&Value::Object(object))?);
+    }
+
+    Ok(())
+}
+
+async fn import(
+    sqlite: &SqlitePool,
+    repo: &Utf8Path,
+    exclude: &GlobSet,
+    mine: bool,
+    max_commits: Option<u32>,
+) -> anyhow::Result<()> {
+    let watermark = import_watermark(sqlite, repo).await?;
+
+    let mine_email = if mine {
+        Some(git_user_email(repo).await?)
+    } else {
+        None
+    };
+
+    let mut command = process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo.as_str())
+        .arg("log")
+        .arg("--name-only")
+        .arg("--format=%x00%H%x1f%ct%x1f%ae");
+
+    if let Some(max_commits) = max_commits {
+        command.arg(format!("--max-count={max_commits}"));
+    }
+
+    let output = command.output().await?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to read Git history");
+    }
+
+    let log = String::from_utf8(output.stdout)?;
+
+    let mut newest_hash = None;
+    // The walked log reached the previous watermark, so it's safe to advance it. If
+    // `--max-commits` cuts the walk off before the old watermark (or the repo's root commit, on
+    // a fresh DB) turns up, there's a gap of un-imported history between the two runs; leave the
+    // watermark alone so the next run (with a larger or no `--max-commits`) can still close it.
+    let mut reached_watermark = false;
+    let mut walked_commits = 0u32;
+    // `git log` walks newest-first, but `record()` always overwrites `last_accessed`, so replay
+    // commits oldest-first to end up with the true latest access time per path.
+    let mut commits = Vec::new();
+
+    for commit in log.split('\0').filter(|commit| !commit.is_empty()) {
+        let Some((header, files)) = commit.split_once('\n') else {
+            continue;
+        };
+
+        let mut fields = header.splitn(3, '\u{1f}');
+        let (Some(hash), Some(time), Some(email)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+
+        if newest_hash.is_none() {
+            newest_hash = Some(hash.to_string());
+        }
+
+        walked_commits += 1;
+
+        if watermark.as_deref() == Some(hash) {
+            reached_watermark = true;
+            break;
+        }
+
+        if mine_email.as_deref().is_some_and(|mine| mine != email) {
+            continue;
+        }
+
+        commits.push((Timestamp::from_second(time.parse()?)?, files));
+    }
+
+    // No prior watermark: the walk only reached the true beginning of history if `--max-commits`
+    // didn't cut it short, i.e. `git log` returned fewer commits than the requested limit.
+    if watermark.is_none() {
+        reached_watermark = max_commits.is_none_or(|max_commits| walked_commits < max_commits);
+    }
+
+    for (time, files) in commits.into_iter().rev() {
+        for file in files.lines().filter(|file| !file.is_empty()) {
+            let path = repo.join(file);
+            if !exclude.is_match(&path) {
+                record(sqlite, repo, &path, Some(&time), None).await?;
+            }
+        }
+    }
+
+    if reached_watermark {
+        if let Some(hash) = newest_hash {
+            set_import_watermark(sqlite, repo, &hash).await?;
+        }
+    } else {
+        tracing::warn!(
+            "--max-commits didn't reach the previous import watermark (or, on a first import, the \
+             start of history); leaving the watermark unchanged so \
+             the skipped commits aren't lost. Re-run with a larger --max-commits (or without it) \
+             to catch up."
+        );
+    }
+
+    Ok(())
+}
+
+async fn git_user_email(repo: &Utf8Path) -> anyhow::Result<String> {
+    let output = process::Command::new("git")
+        .arg("-C")
+        .arg(repo.as_str())
+        .arg("config")
+        .arg("user.email")
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to read Git user email");
+    }
+
+    Ok(str::from_utf8(&output.stdout)?.trim().to_string())
+}
+
+async fn import_watermark(sqlite: &SqlitePool, repo: &Utf8Path) -> anyhow::Result<Option<String>> {
+    let watermark: Option<String> =
+        sqlx::query_scalar("select commit_hash from empath_import where repo = $1")
+            .bind(repo.as_str())
+            .fetch_optional(sqlite)
+            .await?;
+
+    Ok(watermark)
+}
+
+async fn set_import_watermark(
+    sqlite: &SqlitePool,
+    repo: &Utf8Path,
+    commit_hash: &str,
+) -> anyhow::Result<()> {
+    sqlx::query(
         "
-        select path
-        from empath
-        where repo = $1
-        group by path
-        order by count(*) desc
+        insert into empath_import (repo, commit_hash)
+        values ($1, $2)
+        on conflict (repo) do update set commit_hash = excluded.commit_hash
         ",
     )
-    .bind(repo)
-    .fetch_all(sqlite)
+    .bind(repo.as_str())
+    .bind(commit_hash)
+    .execute(sqlite)
     .await?;
 
-    let paths = rows
-        .into_iter()
-        .map(|string| Utf8PathBuf::from(string))
-        .collect();
-
-    Ok(paths)
+    Ok(())
 }